@@ -0,0 +1,68 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Minimal ERC-20 used by the e2e tests: `balanceOf`, `transfer`, `transferFrom` and
+/// `approve` with their Solidity selectors, returning `false` instead of reverting on
+/// insufficient balance or allowance.
+#[ink::contract(env = ink::env::DefaultEnvironment)]
+pub mod erc20_mock {
+    use ink::storage::Mapping;
+    use ink::U256;
+
+    #[ink(storage)]
+    pub struct Erc20Mock {
+        balances: Mapping<Address, U256>,
+        allowances: Mapping<(Address, Address), U256>,
+    }
+
+    impl Erc20Mock {
+        #[ink(constructor)]
+        pub fn new(initial_supply: U256) -> Self {
+            let mut balances = Mapping::default();
+            balances.insert(Self::env().caller(), &initial_supply);
+            Self {
+                balances,
+                allowances: Mapping::default(),
+            }
+        }
+
+        #[ink(message, name = "balanceOf")]
+        pub fn balance_of(&self, owner: Address) -> U256 {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: Address, value: U256) -> bool {
+            let from = self.env().caller();
+            self.move_balance(from, to, value)
+        }
+
+        #[ink(message, name = "transferFrom")]
+        pub fn transfer_from(&mut self, from: Address, to: Address, value: U256) -> bool {
+            let spender = self.env().caller();
+            let allowance = self.allowances.get((from, spender)).unwrap_or_default();
+            if allowance < value || !self.move_balance(from, to, value) {
+                return false;
+            }
+            self.allowances.insert((from, spender), &(allowance - value));
+            true
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, spender: Address, value: U256) -> bool {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &value);
+            true
+        }
+
+        fn move_balance(&mut self, from: Address, to: Address, value: U256) -> bool {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return false;
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            true
+        }
+    }
+}
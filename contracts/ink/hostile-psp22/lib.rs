@@ -0,0 +1,110 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Malicious PSP22 used by the escrow e2e tests: once armed, every `transfer`
+/// tries to re-enter the escrow's `claim` before moving any balance.
+#[ink::contract(env = ink::env::DefaultEnvironment)]
+pub mod hostile_psp22 {
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::{CallFlags, DefaultEnvironment};
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use ink::U256;
+
+    /// How the last attempt to re-enter the escrow ended.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug, ink::storage::traits::StorageLayout))]
+    pub enum Reentry {
+        NotTried,
+        /// The runtime refused the nested call: the escrow called us without `ALLOW_REENTRY`.
+        Denied,
+        /// The escrow ran the nested `claim` and returned this `ClaimError` index.
+        Rejected(u8),
+        Succeeded,
+    }
+
+    #[ink(storage)]
+    pub struct HostilePsp22 {
+        balances: Mapping<Address, U256>,
+        target: Option<(Address, [u8; 32])>, // (escrow, secret) to re-enter with
+        reentry_attempts: u32,
+        reentry_succeeded: bool,
+        last_reentry: Reentry,
+    }
+
+    impl HostilePsp22 {
+        #[ink(constructor)]
+        pub fn new(initial_supply: U256) -> Self {
+            let mut balances = Mapping::default();
+            balances.insert(Self::env().caller(), &initial_supply);
+            Self {
+                balances,
+                target: None,
+                reentry_attempts: 0,
+                reentry_succeeded: false,
+                last_reentry: Reentry::NotTried,
+            }
+        }
+
+        /// Arm the attack: subsequent transfers re-enter `escrow.claim(secret)`.
+        #[ink(message)]
+        pub fn set_attack(&mut self, escrow: Address, secret: [u8; 32]) {
+            self.target = Some((escrow, secret));
+        }
+
+        #[ink(message)]
+        pub fn reentry_attempts(&self) -> u32 {
+            self.reentry_attempts
+        }
+
+        #[ink(message)]
+        pub fn reentry_succeeded(&self) -> bool {
+            self.reentry_succeeded
+        }
+
+        #[ink(message)]
+        pub fn last_reentry(&self) -> Reentry {
+            self.last_reentry
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: Address) -> U256 {
+            self.balances.get(owner).unwrap_or(U256::from(0u8))
+        }
+
+        // Same selector as the PSP22 test token; escrows talk to it as `TokenAbi::LegacyPsp22`
+        #[ink(message, selector = 0xBAF396F5)]
+        pub fn transfer(&mut self, to: Address, value: U256, _data: Vec<u8>) -> Result<(), ()> {
+            if let Some((escrow, secret)) = self.target {
+                self.reentry_attempts = self.reentry_attempts.saturating_add(1);
+                let result = build_call::<DefaultEnvironment>()
+                    .call(escrow)
+                    .call_flags(CallFlags::ALLOW_REENTRY)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(ink::selector_bytes!("claim")))
+                            .push_arg(secret),
+                    )
+                    // ClaimError is a fieldless enum, so its SCALE index decodes as u8
+                    .returns::<Result<(), u8>>()
+                    .try_invoke();
+                self.last_reentry = match result {
+                    Ok(Ok(Ok(()))) => Reentry::Succeeded,
+                    Ok(Ok(Err(code))) => Reentry::Rejected(code),
+                    _ => Reentry::Denied,
+                };
+                if self.last_reentry == Reentry::Succeeded {
+                    self.reentry_succeeded = true;
+                }
+            }
+
+            let from = self.env().caller();
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(());
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            Ok(())
+        }
+    }
+}
@@ -2,246 +2,1920 @@
 
 #[ink::contract(env = ink::env::DefaultEnvironment)]
 pub mod htlc_factory {
-    use htlc_escrow::htlc_escrow::HtlcEscrowRef;
-    use ink::env::call::{build_call, ExecutionInput, Selector};
-    use ink::env::DefaultEnvironment;
+    use htlc_escrow::htlc_escrow::{
+        AssetKind, EscrowOutcome, HashAlgorithm, HtlcEscrowRef, Immutables, Split, TimeBasis,
+        Timelocks, TokenAbi,
+    };
     use ink::prelude::vec::Vec;
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::hash::Keccak256;
     use ink::primitives::H256 as CodeHash;
     use ink::primitives::U256;
+    use ink::sol::FixedBytes;
+    use ink::storage::Mapping;
     use ink::ToAddr;
 
-    // Optional interface marker for clarity (we use explicit selectors for calls)
-    #[ink::trait_definition]
-    pub trait PSP22 {
-        #[ink(message)]
-        fn transfer(&mut self, to: Address, value: Balance, data: Vec<u8>) -> Result<(), ()>;
+    /// Upper bound for any protocol fee (10%).
+    pub const MAX_FEE_BPS: u16 = 1_000;
 
-        #[ink(message)]
-        fn transfer_from(
-            &mut self,
-            from: Address,
-            to: Address,
-            value: Balance,
-            data: Vec<u8>,
-        ) -> Result<(), ()>;
+    /// Most escrows returned by one registry page.
+    pub const MAX_PAGE: u32 = 50;
+
+    /// Escrow code queued by the owner; applicable from `ready_at` (block) on.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug, ink::storage::traits::StorageLayout))]
+    #[cfg_attr(any(ink_abi = "sol", ink_abi = "all"), derive(ink::SolDecode, ink::SolEncode))]
+    pub struct PendingCodeHash {
+        pub code_hash: CodeHash,
+        pub init_code_hash: FixedBytes<32>,
+        pub ready_at: u64,
+    }
+
+    /// Stake a resolver keeps in the factory. Unstaked funds stay slashable in `unbonding`
+    /// until `unlock_at` (block).
+    #[derive(
+        scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq, Default,
+    )]
+    #[cfg_attr(feature = "std", derive(Debug, ink::storage::traits::StorageLayout))]
+    #[cfg_attr(any(ink_abi = "sol", ink_abi = "all"), derive(ink::SolDecode, ink::SolEncode))]
+    pub struct ResolverStake {
+        pub whitelisted: bool, // added by an admin
+        pub stake: U256,
+        pub unbonding: U256,
+        pub unlock_at: u64,
+    }
+
+    /// Registry index an escrow is listed under.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub enum RegistryKey {
+        Hashlock([u8; 32]),
+        Initiator(Address),
+        Beneficiary(Address),
+    }
+
+    /// Everything that fixes an escrow's identity; together with the initiator and an optional
+    /// user salt it derives the instantiation salt, so the address is known before creation.
+    /// `timelocks` are as passed to `create_*`, before deployment stamping.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    #[cfg_attr(any(ink_abi = "sol", ink_abi = "all"), derive(ink::SolDecode, ink::SolEncode))]
+    pub struct EscrowParams {
+        pub token: Option<Address>, // None = native
+        pub token_abi: TokenAbi, // ignored for native escrows, pass the default
+        pub amount: U256, // locked amount, excluding resolver_deposit
+        pub beneficiary: Address,
+        pub splits: Vec<Split>,
+        pub order_hash: FixedBytes<32>, // Fusion+ source order, zero if none
+        pub hashed_secret: FixedBytes<32>,
+        pub timelocks: Timelocks,
+        pub resolver_deposit: U256,
+        pub parts_amount: u16,
+        pub hash_algorithm: HashAlgorithm,
+        pub taker: Option<Address>,
+    }
+
+    /// Errors of the `create_*` messages.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    #[cfg_attr(
+        any(ink_abi = "sol", ink_abi = "all"),
+        derive(ink::SolErrorDecode, ink::SolErrorEncode, ink::SolErrorMetadata)
+    )]
+    pub enum FactoryError {
+        /// This factory already created an escrow at the address the params, initiator and
+        /// salt derive, e.g. the same escrow created twice without a salt.
+        EscrowExists,
+    }
+
+    /// Compact history kept for every escrow created, so outcomes stay queryable
+    /// after finalized escrows are destroyed.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug, ink::storage::traits::StorageLayout))]
+    #[cfg_attr(any(ink_abi = "sol", ink_abi = "all"), derive(ink::SolDecode, ink::SolEncode))]
+    pub struct EscrowRecord {
+        pub hashed_secret: FixedBytes<32>,
+        pub initiator: Address,
+        pub beneficiary: Address,
+        pub created_at: u64, // block
+        pub version: u32, // escrow code version, see get_code_hash_of_version
+        pub outcome: EscrowOutcome,
+        pub finalized_at: u64, // block, 0 while open
     }
 
-    /// Factory that instantiates HtlcEscrow contracts (native or PSP22).
+    /// Factory that instantiates HtlcEscrow contracts (native, PSP22 or ERC-20).
     /// Stores the code hash of the escrow to use for instantiation.
     #[ink(storage)]
     pub struct HtlcFactory {
         escrow_code_hash: CodeHash,
+        escrow_init_code_hash: [u8; 32], // keccak256(escrow code ++ new_for_factory input)
+        escrow_version: u32, // bumped by every apply_code_hash, starts at 1
+        code_hashes: Mapping<u32, CodeHash>, // every escrow code version ever used
+        pending_code_hash: Option<PendingCodeHash>,
+        upgrade_delay: u64, // blocks between propose_code_hash and apply_code_hash
+        rescue_delay_blocks: u64, // after deployment before rescue_funds opens, block-based escrows
+        rescue_delay_seconds: u64, // the same for timestamp-based escrows
         last_escrow: Address,
+        records: Mapping<Address, EscrowRecord>,
+        owner: Address, // upgrades escrow code, manages admins, treasury and fee policy
+        pending_owner: Option<Address>, // must accept_ownership to take over
+        admins: Mapping<Address, ()>, // may change the fee policy and cancel pending upgrades
+        treasury: Address, // receives protocol fees
+        fee_bps: Mapping<Address, u16>, // per asset; zero address = native
+        fee_exempt: Mapping<Address, ()>, // resolvers creating escrows without protocol fee
+        registry_len: Mapping<RegistryKey, u32>,
+        registry: Mapping<(RegistryKey, u32), Address>, // escrows per key in creation order
+        stake_token: Option<Address>, // None = native; PSP22 or ERC-20 otherwise
+        stake_token_abi: TokenAbi,
+        min_stake: U256, // a whitelisted resolver counts as registered from this stake on
+        unbonding_delay: u64, // blocks unstaked funds remain slashable
+        resolvers: Mapping<Address, ResolverStake>,
+        total_staked: U256, // stake + unbonding of all resolvers
+        takers_must_be_registered: bool, // private windows only for registered resolvers
     }
 
-    #[ink(event, anonymous)]
+    /// Indexed by hashlock and parties; events carry at most three topics
+    /// besides the signature topic, so `escrow` and the token are data only.
+    #[ink(event)]
     pub struct EscrowCreated {
         pub escrow: Address,
+        #[ink(topic)]
+        pub initiator: Address,
+        #[ink(topic)]
         pub beneficiary: Address,
-        pub expiry: u64,
+        pub taker: Option<Address>,
+        pub timelocks: Timelocks,
         pub locked_amount: U256,
         pub resolver_deposit: U256,
-        pub hashed_secret: [u8; 32],
-        pub is_psp22: bool,
-        pub psp22_token: Address,
+        #[ink(topic)]
+        pub hashed_secret: FixedBytes<32>,
+        pub hash_algorithm: u8, // 0 = Keccak256, 1 = Sha256, 2 = Blake2b256
+        pub parts_amount: u16,
+        pub asset_kind: u8, // 0 = Native, 1 = PSP22, 2 = ERC-20
+        pub psp22_token: Address, // PSP22 or ERC-20 token, zero if native
+        pub token_abi: u8, // 0 = standard PSP22, 1 = legacy custom selectors, 2 = ERC-20
+        pub protocol_fee_bps: u16, // taken by the escrow on claim, 0 if none or exempt
+        pub immutables: Immutables, // as returned by the escrow's get_immutables
+        pub version: u32, // escrow code version the escrow was instantiated from
+    }
+
+    #[ink(event)]
+    pub struct CodeHashProposed {
+        #[ink(topic)]
+        pub code_hash: CodeHash,
+        pub ready_at: u64, // block
+    }
+
+    #[ink(event)]
+    pub struct CodeHashCancelled {
+        #[ink(topic)]
+        pub code_hash: CodeHash,
+    }
+
+    #[ink(event)]
+    pub struct CodeHashApplied {
+        #[ink(topic)]
+        pub code_hash: CodeHash,
+        pub version: u32,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        pub previous_owner: Address,
+        #[ink(topic)]
+        pub new_owner: Address, // must call accept_ownership
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Address,
+        #[ink(topic)]
+        pub new_owner: Address,
+    }
+
+    #[ink(event)]
+    pub struct AdminChanged {
+        #[ink(topic)]
+        pub account: Address,
+        pub admin: bool,
+    }
+
+    #[ink(event)]
+    pub struct ResolverWhitelisted {
+        #[ink(topic)]
+        pub resolver: Address,
+        pub whitelisted: bool,
+    }
+
+    #[ink(event)]
+    pub struct ResolverStaked {
+        #[ink(topic)]
+        pub resolver: Address,
+        pub amount: U256,
+    }
+
+    #[ink(event)]
+    pub struct ResolverUnstaked {
+        #[ink(topic)]
+        pub resolver: Address,
+        pub amount: U256,
+        pub unlock_at: u64, // block
+    }
+
+    #[ink(event)]
+    pub struct ResolverWithdrawn {
+        #[ink(topic)]
+        pub resolver: Address,
+        pub amount: U256,
+    }
+
+    #[ink(event)]
+    pub struct ResolverSlashed {
+        #[ink(topic)]
+        pub resolver: Address,
+        pub amount: U256, // sent to the treasury
+    }
+
+    #[ink(event)]
+    pub struct EscrowFinalized {
+        #[ink(topic)]
+        pub escrow: Address,
+        pub outcome: EscrowOutcome,
     }
 
     impl HtlcFactory {
-        /// Provide the code hash of the HtlcEscrow contract on deployment.
+        /// Provide the code hash of the HtlcEscrow contract on deployment, and the rescue delay
+        /// applied to every escrow created: in blocks for block-based timelocks, in seconds for
+        /// timestamp-based ones.
+        /// `escrow_init_code_hash` is keccak256 of the escrow code followed by the `new_for_factory`
+        /// selector, computed off-chain; escrow creation fails if it does not match `escrow_code_hash`.
+        /// The caller becomes owner; new escrow code waits `upgrade_delay` blocks before it applies.
+        /// Resolver stakes start out native, with no minimum and `upgrade_delay` as unbonding delay.
         #[ink(constructor)]
-        pub fn new(escrow_code_hash: CodeHash) -> Self {
-            Self { escrow_code_hash, last_escrow: Address::default() }
+        pub fn new(
+            escrow_code_hash: CodeHash,
+            escrow_init_code_hash: FixedBytes<32>,
+            rescue_delay_blocks: u64,
+            rescue_delay_seconds: u64,
+            upgrade_delay: u64,
+        ) -> Self {
+            let mut code_hashes = Mapping::default();
+            code_hashes.insert(1, &escrow_code_hash);
+            Self {
+                escrow_code_hash,
+                escrow_init_code_hash: escrow_init_code_hash.0,
+                escrow_version: 1,
+                code_hashes,
+                pending_code_hash: None,
+                upgrade_delay,
+                rescue_delay_blocks,
+                rescue_delay_seconds,
+                last_escrow: Address::default(),
+                records: Mapping::default(),
+                owner: Self::env().caller(),
+                pending_owner: None,
+                admins: Mapping::default(),
+                treasury: Self::env().caller(),
+                fee_bps: Mapping::default(),
+                fee_exempt: Mapping::default(),
+                registry_len: Mapping::default(),
+                registry: Mapping::default(),
+                stake_token: None,
+                stake_token_abi: TokenAbi::default(),
+                min_stake: U256::from(0),
+                unbonding_delay: upgrade_delay,
+                resolvers: Mapping::default(),
+                total_staked: U256::from(0),
+                takers_must_be_registered: false,
+            }
+        }
+
+        fn ensure_owner(&self) {
+            assert!(self.env().caller() == self.owner, "only owner");
+        }
+
+        /// The owner counts as an admin.
+        fn ensure_admin(&self) {
+            let caller = self.env().caller();
+            assert!(caller == self.owner || self.admins.contains(caller), "only admin");
+        }
+
+        /// With `takers_must_be_registered`, exclusive private windows go to registered resolvers only.
+        fn ensure_taker_allowed(&self, taker: Option<Address>) {
+            if let Some(taker) = taker {
+                assert!(
+                    !self.takers_must_be_registered || self.is_registered_resolver(taker),
+                    "taker is not a registered resolver"
+                );
+            }
+        }
+
+        /// Sends stake held by the factory to `to`.
+        fn pay_stake(&self, to: Address, amount: U256) {
+            let paid = match self.stake_token {
+                None => self.env().transfer(to, amount).is_ok(),
+                Some(token) => self.stake_token_abi.transfer(token, to, amount).is_ok(),
+            };
+            assert!(paid, "stake transfer failed");
+        }
+
+        /// Fee a new escrow of `token` (zero address = native) created by the caller pays.
+        fn protocol_fee_for(&self, token: Address) -> Option<Split> {
+            if self.fee_exempt.contains(self.env().caller()) {
+                return None;
+            }
+            match self.fee_bps.get(token).unwrap_or(0) {
+                0 => None,
+                bps => Some(Split {
+                    recipient: self.treasury,
+                    bps,
+                }),
+            }
+        }
+
+        /// Rescue delay in the offset units of `timelocks` (blocks or seconds).
+        fn rescue_delay_for(&self, timelocks: Timelocks) -> u64 {
+            match timelocks.time_basis() {
+                TimeBasis::BlockNumber => self.rescue_delay_blocks,
+                TimeBasis::Timestamp => self.rescue_delay_seconds,
+            }
+        }
+
+        /// Instantiation salt of the escrow `initiator` creates with `params`.
+        fn escrow_salt(
+            params: &EscrowParams,
+            initiator: Address,
+            salt: Option<FixedBytes<32>>,
+        ) -> [u8; 32] {
+            let salt = salt.map(|salt| salt.0).unwrap_or_default();
+            let input = scale::Encode::encode(&(params, initiator, salt));
+            let mut out = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&input, &mut out);
+            out
+        }
+
+        /// CREATE2 address of a `new_for_factory` escrow instantiated by this factory with `salt`.
+        fn predict_address(&self, salt: [u8; 32]) -> Address {
+            let mut input = Vec::with_capacity(85);
+            input.push(0xff);
+            input.extend_from_slice(self.env().address().as_bytes());
+            input.extend_from_slice(&salt);
+            input.extend_from_slice(&self.escrow_init_code_hash);
+            let mut out = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&input, &mut out);
+            Address::from_slice(&out[12..])
+        }
+
+        /// Instantiates a blank escrow at its predicted address; the caller initializes it.
+        /// Fails before instantiating if an escrow from this factory already used the address,
+        /// whose record would otherwise be overwritten.
+        fn instantiate_blank(
+            &self,
+            params: &EscrowParams,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<HtlcEscrowRef, FactoryError> {
+            let salt = Self::escrow_salt(params, self.env().caller(), salt);
+            if self.records.contains(self.predict_address(salt)) {
+                return Err(FactoryError::EscrowExists);
+            }
+            let escrow = HtlcEscrowRef::new_for_factory()
+                .endowment(U256::from(0))
+                .code_hash(self.escrow_code_hash)
+                .salt_bytes(Some(salt))
+                .instantiate();
+            assert!(
+                escrow.to_addr() == self.predict_address(salt),
+                "escrow_init_code_hash mismatch"
+            );
+            Ok(escrow)
         }
 
         fn instantiate_native(
             &self,
-            beneficiary: Address,
-            hashed_secret: [u8; 32],
-            expiry: u64,
-            resolver_deposit: U256,
-            salt: Option<[u8; 32]>,
+            params: &EscrowParams,
+            protocol_fee: Option<Split>,
+            salt: Option<FixedBytes<32>>,
             endowment: U256,
-        ) -> Address {
+        ) -> Result<Address, FactoryError> {
             // total value is endowment: locked_amount + resolver_deposit
-            let escrow = HtlcEscrowRef::new_native(beneficiary, hashed_secret, expiry, resolver_deposit)
-                .endowment(endowment)
-                .code_hash(self.escrow_code_hash)
-                .salt_bytes(salt)
-                .instantiate();
+            let mut escrow = self.instantiate_blank(params, salt)?;
+            escrow
+                .call_mut()
+                .initialize_native(
+                    params.beneficiary,
+                    params.splits.clone(),
+                    protocol_fee,
+                    params.order_hash,
+                    params.hashed_secret,
+                    params.timelocks,
+                    params.resolver_deposit,
+                    params.parts_amount,
+                    params.hash_algorithm,
+                    params.taker,
+                    self.env().caller(),
+                    self.rescue_delay_for(params.timelocks),
+                )
+                .transferred_value(endowment)
+                .invoke();
             let escrow_addr: Address = escrow.to_addr();
-            escrow_addr
+            Ok(escrow_addr)
         }
 
-        fn instantiate_psp22(
+        fn instantiate_token(
             &self,
             token: Address,
-            amount: U256,
+            params: &EscrowParams,
+            protocol_fee: Option<Split>,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<Address, FactoryError> {
+            // endowment is resolver_deposit; tokens will be transferred via transfer_from below.
+            let mut escrow = self.instantiate_blank(params, salt)?;
+            escrow
+                .call_mut()
+                .initialize_token(
+                    token,
+                    params.token_abi,
+                    params.amount,
+                    params.beneficiary,
+                    params.splits.clone(),
+                    protocol_fee,
+                    params.order_hash,
+                    params.hashed_secret,
+                    params.timelocks,
+                    params.resolver_deposit,
+                    params.parts_amount,
+                    params.hash_algorithm,
+                    params.taker,
+                    self.env().caller(),
+                    self.rescue_delay_for(params.timelocks),
+                )
+                .transferred_value(params.resolver_deposit)
+                .invoke();
+            let escrow_addr: Address = escrow.to_addr();
+            Ok(escrow_addr)
+        }
+
+        fn record_created(
+            &mut self,
+            escrow: Address,
+            hashed_secret: FixedBytes<32>,
             beneficiary: Address,
-            hashed_secret: [u8; 32],
-            expiry: u64,
-            resolver_deposit: U256,
-            salt: Option<[u8; 32]>,
-        ) -> Address {
-            // endowment is resolver_deposit; PSP22 will be transferred via transfer_from below.
-            let escrow = HtlcEscrowRef::new_psp22(
-                token,
-                amount,
-                beneficiary,
+        ) {
+            let record = EscrowRecord {
                 hashed_secret,
-                expiry,
-                resolver_deposit,
-            )
-            .endowment(resolver_deposit)
-            .code_hash(self.escrow_code_hash)
-            .salt_bytes(salt)
-            .instantiate();
-            let escrow_addr: Address = escrow.to_addr();
-            escrow_addr
+                initiator: self.env().caller(),
+                beneficiary,
+                created_at: self.env().block_number().into(),
+                version: self.escrow_version,
+                outcome: EscrowOutcome::Open,
+                finalized_at: 0,
+            };
+            self.records.insert(escrow, &record);
+            self.last_escrow = escrow;
+
+            self.register(RegistryKey::Hashlock(hashed_secret.0), escrow);
+            self.register(RegistryKey::Initiator(record.initiator), escrow);
+            self.register(RegistryKey::Beneficiary(beneficiary), escrow);
+        }
+
+        fn register(&mut self, key: RegistryKey, escrow: Address) {
+            let len = self.registry_len.get(key).unwrap_or(0);
+            self.registry.insert((key, len), &escrow);
+            self.registry_len.insert(key, &len.saturating_add(1));
+        }
+
+        /// Up to `MAX_PAGE` escrows listed under `key`, oldest first, starting at `offset`.
+        fn registry_page(&self, key: RegistryKey, offset: u32, limit: u32) -> Vec<Address> {
+            let len = self.registry_len.get(key).unwrap_or(0);
+            let end = offset.saturating_add(limit.min(MAX_PAGE)).min(len);
+            (offset..end)
+                .filter_map(|index| self.registry.get((key, index)))
+                .collect()
+        }
+
+        /// Timelocks as stamped by an escrow instantiated in this block.
+        fn deployed_timelocks(&self, timelocks: Timelocks) -> Timelocks {
+            timelocks.deployed_now()
+        }
+
+        /// Immutables of the escrow the caller just created with `params`.
+        fn immutables_of(&self, params: &EscrowParams, protocol_fee: Option<Split>) -> Immutables {
+            Immutables {
+                order_hash: params.order_hash,
+                hashed_secret: params.hashed_secret,
+                initiator: self.env().caller(),
+                beneficiary: params.beneficiary,
+                taker: params.taker,
+                token: params.token.unwrap_or_default(),
+                amount: params.amount,
+                resolver_deposit: params.resolver_deposit,
+                timelocks: self.deployed_timelocks(params.timelocks),
+                hash_algorithm: params.hash_algorithm,
+                parts_amount: params.parts_amount,
+                splits: params.splits.clone(),
+                protocol_fee,
+            }
         }
 
         /// Create a native-balance escrow.
         /// Attach value = locked_amount + resolver_deposit.
+        /// Pass a non-zero `parts_amount` with a Merkle root as `hashed_secret` to allow partial fills.
+        /// `splits` send basis-point shares of every claim payout to other recipients (e.g. fees).
+        /// `order_hash` is the Fusion+ order this escrow fills, zero if none; see `Immutables`.
+        /// `salt` only needs to be unique among the caller's escrows with identical parameters;
+        /// `None` is the fixed all-zero salt, so creating the very same escrow twice without one
+        /// fails with `FactoryError::EscrowExists`.
         #[ink(message, payable)]
         pub fn create_native_escrow(
             &mut self,
             beneficiary: Address,
-            hashed_secret: [u8; 32],
-            expiry: u64,
+            splits: Vec<Split>,
+            order_hash: FixedBytes<32>,
+            hashed_secret: FixedBytes<32>,
+            timelocks: Timelocks,
             resolver_deposit: U256,
-            salt: Option<[u8; 32]>,
-        ) -> Address {
+            parts_amount: u16,
+            hash_algorithm: HashAlgorithm,
+            taker: Option<Address>,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<Address, FactoryError> {
             let total: U256 = self.env().transferred_value();
+            self.ensure_taker_allowed(taker);
             assert!(resolver_deposit > U256::from(0), "resolver_deposit required");
             assert!(total > resolver_deposit, "insufficient value for lock");
             let locked_amount: U256 = total
                 .checked_sub(resolver_deposit)
                 .expect("underflow on locked_amount");
 
-            let escrow_addr = self.instantiate_native(
+            let protocol_fee = self.protocol_fee_for(Address::default());
+            let params = EscrowParams {
+                token: None,
+                token_abi: TokenAbi::default(),
+                amount: locked_amount,
                 beneficiary,
+                splits,
+                order_hash,
                 hashed_secret,
-                expiry,
+                timelocks,
                 resolver_deposit,
-                salt,
-                total,
-            );
-            self.last_escrow = escrow_addr;
+                parts_amount,
+                hash_algorithm,
+                taker,
+            };
+            let escrow_addr = self.instantiate_native(&params, protocol_fee, salt, total)?;
+            self.record_created(escrow_addr, hashed_secret, beneficiary);
 
             self.env().emit_event(EscrowCreated {
                 escrow: escrow_addr,
+                initiator: self.env().caller(),
                 beneficiary,
-                expiry,
+                taker,
+                timelocks: self.deployed_timelocks(timelocks),
                 locked_amount,
                 resolver_deposit,
                 hashed_secret,
-                is_psp22: false,
+                hash_algorithm: hash_algorithm.code(),
+                parts_amount,
+                asset_kind: AssetKind::Native.code(),
                 psp22_token: Address::default(),
+                token_abi: TokenAbi::default().code(),
+                protocol_fee_bps: protocol_fee.map(|fee| fee.bps).unwrap_or(0),
+                immutables: self.immutables_of(&params, protocol_fee),
+                version: self.escrow_version,
             });
 
-            escrow_addr
+            Ok(escrow_addr)
         }
 
         /// Create a PSP22 escrow by:
         /// 1) Instantiating escrow with endowment = resolver_deposit (attach value == resolver_deposit).
         /// 2) Pulling tokens from the caller into the new escrow via transfer_from (caller must approve this contract beforehand).
+        /// `token_abi` selects the token's calling convention (standard PSP22 or our legacy test token).
+        /// `salt` as for `create_native_escrow`.
         #[ink(message, payable)]
         pub fn create_psp22_escrow(
             &mut self,
             token: Address,
+            token_abi: TokenAbi,
             amount: U256,
             beneficiary: Address,
-            hashed_secret: [u8; 32],
-            expiry: u64,
+            splits: Vec<Split>,
+            order_hash: FixedBytes<32>,
+            hashed_secret: FixedBytes<32>,
+            timelocks: Timelocks,
             resolver_deposit: U256,
-            salt: Option<[u8; 32]>,
-        ) -> Address {
-            let value: U256 = self.env().transferred_value();
-            assert!(resolver_deposit > U256::from(0), "resolver_deposit required");
-            assert!(value >= resolver_deposit, "attach native deposit only");
-            assert!(amount > U256::from(0), "zero amount");
+            parts_amount: u16,
+            hash_algorithm: HashAlgorithm,
+            taker: Option<Address>,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<Address, FactoryError> {
+            assert!(token_abi != TokenAbi::Erc20, "use create_erc20_escrow for ERC-20 tokens");
+            let params = EscrowParams {
+                token: Some(token),
+                token_abi: token_abi,
+                amount,
+                beneficiary,
+                splits,
+                order_hash,
+                hashed_secret,
+                timelocks,
+                resolver_deposit,
+                parts_amount,
+                hash_algorithm,
+                taker,
+            };
+            self.create_token_escrow(token, params, salt)
+        }
 
-            let escrow_addr = self.instantiate_psp22(
-                token,
+        /// Create an ERC-20 escrow (Solidity ABI token such as a pallet-assets precompile).
+        /// Same flow and `salt` as `create_psp22_escrow`; approve this contract on the token
+        /// beforehand.
+        #[ink(message, payable)]
+        pub fn create_erc20_escrow(
+            &mut self,
+            token: Address,
+            amount: U256,
+            beneficiary: Address,
+            splits: Vec<Split>,
+            order_hash: FixedBytes<32>,
+            hashed_secret: FixedBytes<32>,
+            timelocks: Timelocks,
+            resolver_deposit: U256,
+            parts_amount: u16,
+            hash_algorithm: HashAlgorithm,
+            taker: Option<Address>,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<Address, FactoryError> {
+            let params = EscrowParams {
+                token: Some(token),
+                token_abi: TokenAbi::Erc20,
                 amount,
                 beneficiary,
+                splits,
+                order_hash,
                 hashed_secret,
-                expiry,
+                timelocks,
                 resolver_deposit,
-                salt,
-            );
-            self.last_escrow = escrow_addr;
+                parts_amount,
+                hash_algorithm,
+                taker,
+            };
+            self.create_token_escrow(token, params, salt)
+        }
+
+        /// Shared body of `create_psp22_escrow` and `create_erc20_escrow`; `params.token` is
+        /// `Some(token)`.
+        fn create_token_escrow(
+            &mut self,
+            token: Address,
+            params: EscrowParams,
+            salt: Option<FixedBytes<32>>,
+        ) -> Result<Address, FactoryError> {
+            let value: U256 = self.env().transferred_value();
+            self.ensure_taker_allowed(params.taker);
+            assert!(params.resolver_deposit > U256::from(0), "resolver_deposit required");
+            assert!(value >= params.resolver_deposit, "attach native deposit only");
+            assert!(params.amount > U256::from(0), "zero amount");
 
-            // Move PSP22 from caller to escrow using explicit selector for transfer_from
-            // Selector matches our PSP22 test token #[ink(message, selector = 0x54B3C76F)]
+            let protocol_fee = self.protocol_fee_for(token);
+            let escrow_addr = self.instantiate_token(token, &params, protocol_fee, salt)?;
+            self.record_created(escrow_addr, params.hashed_secret, params.beneficiary);
+
+            // Move tokens from caller to escrow
             let caller = self.env().caller();
-            match build_call::<DefaultEnvironment>()
-                .call(token)
-                .exec_input(
-                    ExecutionInput::new(Selector::new([0x54, 0xB3, 0xC7, 0x6F]))
-                        .push_arg(caller)
-                        .push_arg(escrow_addr)
-                        .push_arg(amount)
-                        .push_arg(Vec::<u8>::new()),
-                )
-                .returns::<Result<(), ()>>()
-                .invoke()
+            if params
+                .token_abi
+                .transfer_from(token, caller, escrow_addr, params.amount)
+                .is_err()
             {
-                Ok(()) => {}
-                _ => panic!("psp22 transfer_from failed (check approval and balance)"),
+                panic!("token transfer_from failed (check approval and balance)");
             }
 
             self.env().emit_event(EscrowCreated {
                 escrow: escrow_addr,
-                beneficiary,
-                expiry,
-                locked_amount: amount,
-                resolver_deposit,
-                hashed_secret,
-                is_psp22: true,
+                initiator: self.env().caller(),
+                beneficiary: params.beneficiary,
+                taker: params.taker,
+                timelocks: self.deployed_timelocks(params.timelocks),
+                locked_amount: params.amount,
+                resolver_deposit: params.resolver_deposit,
+                hashed_secret: params.hashed_secret,
+                hash_algorithm: params.hash_algorithm.code(),
+                parts_amount: params.parts_amount,
+                asset_kind: if params.token_abi == TokenAbi::Erc20 {
+                    AssetKind::Erc20.code()
+                } else {
+                    AssetKind::PSP22.code()
+                },
                 psp22_token: token,
+                token_abi: params.token_abi.code(),
+                protocol_fee_bps: protocol_fee.map(|fee| fee.bps).unwrap_or(0),
+                immutables: self.immutables_of(&params, protocol_fee),
+                version: self.escrow_version,
             });
 
-            escrow_addr
+            Ok(escrow_addr)
         }
 
-        /// Deposit PSP22 tokens into an already deployed escrow by pulling from the caller via transfer_from.
+        /// Deposit PSP22 or ERC-20 tokens into an already deployed escrow by pulling from the caller via transfer_from.
         /// The caller must have approved this factory as spender on the PSP22 token for at least `amount`.
         #[ink(message)]
         pub fn deposit_psp22(
             &mut self,
             token: Address,
+            token_abi: TokenAbi,
             escrow: Address,
             amount: U256,
         ) {
             let caller = self.env().caller();
             // Perform PSP22 transfer_from(caller -> escrow)
-            match build_call::<DefaultEnvironment>()
-                .call(token)
-                .exec_input(
-                    ExecutionInput::new(Selector::new([0x54, 0xB3, 0xC7, 0x6F])) // transfer_from
-                        .push_arg(caller)
-                        .push_arg(escrow)
-                        .push_arg(amount)
-                        .push_arg(Vec::<u8>::new()),
-                )
-                .returns::<Result<(), ()>>()
-                .invoke()
-            {
-                Ok(()) => {}
-                _ => panic!("psp22 deposit transfer_from failed (check approval and balance)"),
+            if token_abi.transfer_from(token, caller, escrow, amount).is_err() {
+                panic!("psp22 deposit transfer_from failed (check approval and balance)");
             }
         }
 
+        /// Called by an escrow created here once it is claimed in full or refunded.
+        #[ink(message)]
+        pub fn record_outcome(&mut self, outcome: EscrowOutcome) {
+            let escrow = self.env().caller();
+            let mut record = self.records.get(escrow).expect("unknown escrow");
+            assert!(record.outcome == EscrowOutcome::Open, "already finalized");
+            assert!(outcome != EscrowOutcome::Open, "outcome must be final");
+            record.outcome = outcome;
+            record.finalized_at = self.env().block_number().into();
+            self.records.insert(escrow, &record);
+
+            self.env().emit_event(EscrowFinalized { escrow, outcome });
+        }
+
+        /// Returns the recorded history of an escrow created by this factory.
+        #[ink(message)]
+        pub fn get_escrow_record(&self, escrow: Address) -> Option<EscrowRecord> {
+            self.records.get(escrow)
+        }
+
+        /// Escrows created with `hashed_secret`, oldest first; at most `MAX_PAGE` per call.
+        ///
+        /// Anyone can create escrows under any hashlock or naming any beneficiary, so
+        /// entries are unverified: check `get_immutables` of an escrow before relying on it.
+        #[ink(message)]
+        pub fn get_escrows_by_hashlock(
+            &self,
+            hashed_secret: FixedBytes<32>,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<Address> {
+            self.registry_page(RegistryKey::Hashlock(hashed_secret.0), offset, limit)
+        }
+
+        /// Escrows funded by `initiator`, oldest first; at most `MAX_PAGE` per call.
+        #[ink(message)]
+        pub fn get_escrows_by_initiator(
+            &self,
+            initiator: Address,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<Address> {
+            self.registry_page(RegistryKey::Initiator(initiator), offset, limit)
+        }
+
+        /// Escrows paying `beneficiary`, oldest first; at most `MAX_PAGE` per call.
+        /// Unverified like `get_escrows_by_hashlock`.
+        #[ink(message)]
+        pub fn get_escrows_by_beneficiary(
+            &self,
+            beneficiary: Address,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<Address> {
+            self.registry_page(RegistryKey::Beneficiary(beneficiary), offset, limit)
+        }
+
+        #[ink(message)]
+        pub fn count_escrows_by_hashlock(&self, hashed_secret: FixedBytes<32>) -> u32 {
+            self.registry_len.get(RegistryKey::Hashlock(hashed_secret.0)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn count_escrows_by_initiator(&self, initiator: Address) -> u32 {
+            self.registry_len.get(RegistryKey::Initiator(initiator)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn count_escrows_by_beneficiary(&self, beneficiary: Address) -> u32 {
+            self.registry_len.get(RegistryKey::Beneficiary(beneficiary)).unwrap_or(0)
+        }
+
         /// Returns the last created escrow address.
         #[ink(message)]
         pub fn get_last_escrow(&self) -> Address {
             self.last_escrow
         }
 
+        /// Returns the rescue delay, in blocks, passed to new block-based escrows.
+        #[ink(message)]
+        pub fn get_rescue_delay_blocks(&self) -> u64 {
+            self.rescue_delay_blocks
+        }
+
+        /// Returns the rescue delay, in seconds, passed to new timestamp-based escrows.
+        #[ink(message)]
+        pub fn get_rescue_delay_seconds(&self) -> u64 {
+            self.rescue_delay_seconds
+        }
+
         /// Returns the configured escrow code hash.
         #[ink(message)]
         pub fn get_escrow_code_hash(&self) -> CodeHash {
             self.escrow_code_hash
         }
+
+        /// Returns the init code hash escrow addresses are predicted from.
+        #[ink(message)]
+        pub fn get_escrow_init_code_hash(&self) -> FixedBytes<32> {
+            FixedBytes(self.escrow_init_code_hash)
+        }
+
+        /// Address the escrow `initiator` would get by calling `create_*` with `params` and `salt`,
+        /// so counterparties can fund or watch it before it exists.
+        /// A `None` salt is the fixed all-zero salt: identical `params` from one initiator map to
+        /// one address, and only the first `create_*` for it succeeds.
+        #[ink(message)]
+        pub fn compute_escrow_address(
+            &self,
+            params: EscrowParams,
+            initiator: Address,
+            salt: Option<FixedBytes<32>>,
+        ) -> Address {
+            self.predict_address(Self::escrow_salt(&params, initiator, salt))
+        }
+
+        /// Owner only. Sets the account receiving protocol fees of escrows created from now on.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: Address) {
+            self.ensure_owner();
+            self.treasury = treasury;
+        }
+
+        /// Admin only. Sets the protocol fee for new escrows of `token` (None = native).
+        /// Existing escrows keep the fee they were created with.
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, token: Option<Address>, bps: u16) {
+            self.ensure_admin();
+            assert!(bps <= MAX_FEE_BPS, "fee too high");
+            self.fee_bps.insert(token.unwrap_or_default(), &bps);
+        }
+
+        /// Admin only. Exempts (or stops exempting) escrows created by `resolver` from the fee.
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, resolver: Address, exempt: bool) {
+            self.ensure_admin();
+            if exempt {
+                self.fee_exempt.insert(resolver, &());
+            } else {
+                self.fee_exempt.remove(resolver);
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> Address {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn get_treasury(&self) -> Address {
+            self.treasury
+        }
+
+        /// Protocol fee in basis points for new escrows of `token` (None = native).
+        #[ink(message)]
+        pub fn get_fee_bps(&self, token: Option<Address>) -> u16 {
+            self.fee_bps.get(token.unwrap_or_default()).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, resolver: Address) -> bool {
+            self.fee_exempt.contains(resolver)
+        }
+
+        /// Owner only. Queues new escrow code; `apply_code_hash` switches to it once
+        /// `upgrade_delay` blocks have passed. Replaces any earlier proposal.
+        /// `init_code_hash` is the matching keccak256(code ++ new_for_factory selector).
+        #[ink(message)]
+        pub fn propose_code_hash(&mut self, code_hash: CodeHash, init_code_hash: FixedBytes<32>) {
+            self.ensure_owner();
+            let now: u64 = self.env().block_number().into();
+            let ready_at = now.saturating_add(self.upgrade_delay);
+            self.pending_code_hash = Some(PendingCodeHash {
+                code_hash,
+                init_code_hash,
+                ready_at,
+            });
+            self.env().emit_event(CodeHashProposed {
+                code_hash,
+                ready_at,
+            });
+        }
+
+        /// Owner only. Switches new escrows to the pending code and bumps the escrow version.
+        /// Escrows created earlier keep their code and version.
+        #[ink(message)]
+        pub fn apply_code_hash(&mut self) {
+            self.ensure_owner();
+            let pending = self.pending_code_hash.expect("no pending code hash");
+            let now: u64 = self.env().block_number().into();
+            assert!(now >= pending.ready_at, "upgrade delay not elapsed");
+
+            self.escrow_code_hash = pending.code_hash;
+            self.escrow_init_code_hash = pending.init_code_hash.0;
+            self.escrow_version = self.escrow_version.checked_add(1).expect("version overflow");
+            self.code_hashes.insert(self.escrow_version, &pending.code_hash);
+            self.pending_code_hash = None;
+            self.env().emit_event(CodeHashApplied {
+                code_hash: pending.code_hash,
+                version: self.escrow_version,
+            });
+        }
+
+        /// Admin only. Drops the pending code hash, e.g. when the proposal is found faulty.
+        #[ink(message)]
+        pub fn cancel_code_hash(&mut self) {
+            self.ensure_admin();
+            let pending = self.pending_code_hash.take().expect("no pending code hash");
+            self.env().emit_event(CodeHashCancelled {
+                code_hash: pending.code_hash,
+            });
+        }
+
+        #[ink(message)]
+        pub fn get_pending_code_hash(&self) -> Option<PendingCodeHash> {
+            self.pending_code_hash
+        }
+
+        #[ink(message)]
+        pub fn get_upgrade_delay(&self) -> u64 {
+            self.upgrade_delay
+        }
+
+        /// Version of the escrow code new escrows are instantiated from.
+        #[ink(message)]
+        pub fn get_escrow_version(&self) -> u32 {
+            self.escrow_version
+        }
+
+        /// Escrow code of `version`; with `EscrowRecord::version` this traces any escrow's code.
+        #[ink(message)]
+        pub fn get_code_hash_of_version(&self, version: u32) -> Option<CodeHash> {
+            self.code_hashes.get(version)
+        }
+
+        /// Owner only. Offers ownership to `new_owner`, who takes over with `accept_ownership`.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: Address) {
+            self.ensure_owner();
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: self.owner,
+                new_owner,
+            });
+        }
+
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) {
+            let caller = self.env().caller();
+            assert!(self.pending_owner == Some(caller), "not pending owner");
+            let previous_owner = self.owner;
+            self.owner = caller;
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: caller,
+            });
+        }
+
+        #[ink(message)]
+        pub fn get_pending_owner(&self) -> Option<Address> {
+            self.pending_owner
+        }
+
+        /// Owner only. Grants or revokes the admin role.
+        #[ink(message)]
+        pub fn set_admin(&mut self, account: Address, admin: bool) {
+            self.ensure_owner();
+            if admin {
+                self.admins.insert(account, &());
+            } else {
+                self.admins.remove(account);
+            }
+            self.env().emit_event(AdminChanged { account, admin });
+        }
+
+        #[ink(message)]
+        pub fn is_admin(&self, account: Address) -> bool {
+            self.admins.contains(account)
+        }
+
+        /// Admin only. Configures resolver staking. The stake token (None = native) can only
+        /// change while nothing is staked.
+        #[ink(message)]
+        pub fn set_staking_policy(
+            &mut self,
+            stake_token: Option<Address>,
+            stake_token_abi: TokenAbi,
+            min_stake: U256,
+            unbonding_delay: u64,
+        ) {
+            self.ensure_admin();
+            if stake_token != self.stake_token || stake_token_abi != self.stake_token_abi {
+                assert!(self.total_staked == U256::from(0), "stakes outstanding");
+            }
+            self.stake_token = stake_token;
+            self.stake_token_abi = stake_token_abi;
+            self.min_stake = min_stake;
+            self.unbonding_delay = unbonding_delay;
+        }
+
+        /// Admin only. Limits (or stops limiting) escrow takers to registered resolvers.
+        #[ink(message)]
+        pub fn set_takers_must_be_registered(&mut self, required: bool) {
+            self.ensure_admin();
+            self.takers_must_be_registered = required;
+        }
+
+        /// Admin only. Adds a resolver to (or removes it from) the whitelist; its stake is kept.
+        #[ink(message)]
+        pub fn set_resolver_whitelisted(&mut self, resolver: Address, whitelisted: bool) {
+            self.ensure_admin();
+            let mut entry = self.resolvers.get(resolver).unwrap_or_default();
+            entry.whitelisted = whitelisted;
+            self.resolvers.insert(resolver, &entry);
+            self.env().emit_event(ResolverWhitelisted {
+                resolver,
+                whitelisted,
+            });
+        }
+
+        /// Adds `amount` to the caller's stake. Attach it as value for native stakes;
+        /// otherwise approve this factory on the stake token beforehand.
+        #[ink(message, payable)]
+        pub fn stake(&mut self, amount: U256) {
+            assert!(amount > U256::from(0), "zero amount");
+            let resolver = self.env().caller();
+            let value: U256 = self.env().transferred_value();
+            match self.stake_token {
+                None => assert!(value == amount, "attach the stake as value"),
+                Some(token) => {
+                    assert!(value == U256::from(0), "native value not accepted");
+                    let this = self.env().address();
+                    if self
+                        .stake_token_abi
+                        .transfer_from(token, resolver, this, amount)
+                        .is_err()
+                    {
+                        panic!("stake transfer_from failed (check approval and balance)");
+                    }
+                }
+            }
+
+            let mut entry = self.resolvers.get(resolver).unwrap_or_default();
+            entry.stake = entry.stake.checked_add(amount).expect("stake overflow");
+            self.resolvers.insert(resolver, &entry);
+            self.total_staked = self.total_staked.checked_add(amount).expect("stake overflow");
+            self.env().emit_event(ResolverStaked { resolver, amount });
+        }
+
+        /// Moves `amount` of the caller's stake to unbonding, where it stays slashable
+        /// for `unbonding_delay` blocks; further unstaking restarts the delay.
+        #[ink(message)]
+        pub fn unstake(&mut self, amount: U256) {
+            let resolver = self.env().caller();
+            let mut entry = self.resolvers.get(resolver).expect("not a resolver");
+            entry.stake = entry.stake.checked_sub(amount).expect("insufficient stake");
+            entry.unbonding = entry.unbonding.checked_add(amount).expect("stake overflow");
+            let now: u64 = self.env().block_number().into();
+            entry.unlock_at = now.saturating_add(self.unbonding_delay);
+            self.resolvers.insert(resolver, &entry);
+            self.env().emit_event(ResolverUnstaked {
+                resolver,
+                amount,
+                unlock_at: entry.unlock_at,
+            });
+        }
+
+        /// Pays out the caller's unbonded stake once `unlock_at` has passed.
+        #[ink(message)]
+        pub fn withdraw_unstaked(&mut self) {
+            let resolver = self.env().caller();
+            let mut entry = self.resolvers.get(resolver).expect("not a resolver");
+            let now: u64 = self.env().block_number().into();
+            assert!(now >= entry.unlock_at, "still unbonding");
+            let amount = entry.unbonding;
+            assert!(amount > U256::from(0), "nothing to withdraw");
+
+            entry.unbonding = U256::from(0);
+            self.resolvers.insert(resolver, &entry);
+            self.total_staked = self.total_staked.checked_sub(amount).expect("stake underflow");
+            self.pay_stake(resolver, amount);
+            self.env().emit_event(ResolverWithdrawn { resolver, amount });
+        }
+
+        /// Admin only. Takes up to `amount` from a misbehaving resolver, active stake first,
+        /// then unbonding funds, and sends it to the treasury.
+        #[ink(message)]
+        pub fn slash(&mut self, resolver: Address, amount: U256) {
+            self.ensure_admin();
+            let mut entry = self.resolvers.get(resolver).expect("not a resolver");
+            let from_stake = amount.min(entry.stake);
+            let from_unbonding = amount.saturating_sub(from_stake).min(entry.unbonding);
+            entry.stake = entry.stake.saturating_sub(from_stake);
+            entry.unbonding = entry.unbonding.saturating_sub(from_unbonding);
+            let slashed = from_stake.saturating_add(from_unbonding);
+            assert!(slashed > U256::from(0), "nothing to slash");
+
+            self.resolvers.insert(resolver, &entry);
+            self.total_staked = self.total_staked.checked_sub(slashed).expect("stake underflow");
+            self.pay_stake(self.treasury, slashed);
+            self.env().emit_event(ResolverSlashed {
+                resolver,
+                amount: slashed,
+            });
+        }
+
+        /// Whitelisted with at least `min_stake` staked (unbonding funds do not count).
+        #[ink(message)]
+        pub fn is_registered_resolver(&self, account: Address) -> bool {
+            self.resolvers
+                .get(account)
+                .map(|entry| entry.whitelisted && entry.stake >= self.min_stake)
+                .unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn get_resolver(&self, account: Address) -> Option<ResolverStake> {
+            self.resolvers.get(account)
+        }
+
+        /// Stake token (None = native), its ABI, minimum stake and unbonding delay in blocks.
+        #[ink(message)]
+        pub fn get_staking_policy(&self) -> (Option<Address>, TokenAbi, U256, u64) {
+            (
+                self.stake_token,
+                self.stake_token_abi,
+                self.min_stake,
+                self.unbonding_delay,
+            )
+        }
+
+        #[ink(message)]
+        pub fn get_takers_must_be_registered(&self) -> bool {
+            self.takers_must_be_registered
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+
+        /// Factory owned by alice, with a rescue delay of 100 blocks or 600 seconds
+        /// and a 10-block upgrade delay.
+        fn factory() -> HtlcFactory {
+            HtlcFactory::new(CodeHash::from([1u8; 32]), FixedBytes([2u8; 32]), 100, 600, 10)
+        }
+
+        fn set_caller(account: Address) {
+            ink::env::test::set_caller::<DefaultEnvironment>(account);
+        }
+
+        fn advance(blocks: u32) {
+            for _ in 0..blocks {
+                ink::env::test::advance_block::<DefaultEnvironment>();
+            }
+        }
+
+        fn balance(account: Address) -> U256 {
+            ink::env::test::get_account_balance::<DefaultEnvironment>(account).unwrap_or_default()
+        }
+
+        /// Native stake by `resolver`; off-chain calls do not credit the attached value,
+        /// so the factory's balance is topped up by hand.
+        fn stake(factory: &mut HtlcFactory, resolver: Address, amount: u64) {
+            set_caller(resolver);
+            ink::env::test::set_value_transferred(U256::from(amount));
+            factory.stake(U256::from(amount));
+            ink::env::test::set_value_transferred(U256::from(0));
+            let callee = ink::env::test::callee::<DefaultEnvironment>();
+            ink::env::test::set_account_balance(callee, balance(callee) + U256::from(amount));
+        }
+
+        fn stake_of(factory: &HtlcFactory, resolver: Address) -> (U256, U256) {
+            let entry = factory.get_resolver(resolver).unwrap_or_default();
+            (entry.stake, entry.unbonding)
+        }
+
+        #[ink::test]
+        fn rescue_delay_follows_the_time_basis() {
+            let factory = factory();
+            let timelocks = Timelocks::new(2, 4, 6, 8);
+            assert_eq!(factory.rescue_delay_for(timelocks), 100);
+            let timelocks = timelocks.with_time_basis(TimeBasis::Timestamp);
+            assert_eq!(factory.rescue_delay_for(timelocks), 600);
+            assert_eq!(factory.get_rescue_delay_blocks(), 100);
+            assert_eq!(factory.get_rescue_delay_seconds(), 600);
+        }
+
+        #[ink::test]
+        fn registry_pages_are_bounded() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            let hashlock = FixedBytes([1u8; 32]);
+            let escrows: Vec<Address> = (0..60u8).map(|i| Address::from([i; 20])).collect();
+            for escrow in &escrows[..3] {
+                factory.record_created(*escrow, hashlock, accounts.bob);
+            }
+            assert_eq!(factory.count_escrows_by_hashlock(hashlock), 3);
+            assert_eq!(factory.count_escrows_by_initiator(accounts.alice), 3);
+            assert_eq!(factory.count_escrows_by_beneficiary(accounts.bob), 3);
+            assert_eq!(factory.count_escrows_by_hashlock(FixedBytes([2u8; 32])), 0);
+            assert_eq!(factory.get_last_escrow(), escrows[2]);
+
+            assert_eq!(factory.get_escrows_by_hashlock(hashlock, 0, 2), escrows[..2].to_vec());
+            assert_eq!(factory.get_escrows_by_hashlock(hashlock, 2, 10), escrows[2..3].to_vec());
+            assert_eq!(factory.get_escrows_by_initiator(accounts.alice, 3, 10), Vec::new());
+            assert_eq!(factory.get_escrows_by_beneficiary(accounts.bob, u32::MAX, 10), Vec::new());
+            assert_eq!(factory.get_escrows_by_beneficiary(accounts.bob, 1, 0), Vec::new());
+
+            for escrow in &escrows[3..] {
+                factory.record_created(*escrow, hashlock, accounts.bob);
+            }
+            let first = factory.get_escrows_by_hashlock(hashlock, 0, u32::MAX);
+            assert_eq!(first, escrows[..MAX_PAGE as usize].to_vec());
+            let rest = factory.get_escrows_by_hashlock(hashlock, MAX_PAGE, u32::MAX);
+            assert_eq!(rest, escrows[MAX_PAGE as usize..].to_vec());
+        }
+
+        #[ink::test]
+        fn applied_code_hash_bumps_the_version() {
+            let mut factory = factory();
+            let old = factory.get_escrow_code_hash();
+            let new = CodeHash::from([5u8; 32]);
+            factory.propose_code_hash(new, FixedBytes([6u8; 32]));
+            assert_eq!(factory.get_escrow_code_hash(), old);
+
+            advance(10);
+            factory.apply_code_hash();
+            assert_eq!(factory.get_escrow_version(), 2);
+            assert_eq!(factory.get_escrow_code_hash(), new);
+            assert_eq!(factory.get_escrow_init_code_hash(), FixedBytes([6u8; 32]));
+            assert_eq!(factory.get_pending_code_hash(), None);
+            assert_eq!(factory.get_code_hash_of_version(1), Some(old));
+            assert_eq!(factory.get_code_hash_of_version(2), Some(new));
+            assert_eq!(factory.get_code_hash_of_version(3), None);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "upgrade delay not elapsed")]
+        fn code_hash_waits_for_the_upgrade_delay() {
+            let mut factory = factory();
+            factory.propose_code_hash(CodeHash::from([5u8; 32]), FixedBytes([6u8; 32]));
+            advance(9);
+            factory.apply_code_hash();
+        }
+
+        #[ink::test]
+        fn admins_cancel_pending_code_hash() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.set_admin(accounts.bob, true);
+            factory.propose_code_hash(CodeHash::from([5u8; 32]), FixedBytes([6u8; 32]));
+
+            set_caller(accounts.bob);
+            factory.cancel_code_hash();
+            assert_eq!(factory.get_pending_code_hash(), None);
+            assert_eq!(factory.get_escrow_version(), 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "only admin")]
+        fn cancel_code_hash_is_admin_only() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.propose_code_hash(CodeHash::from([5u8; 32]), FixedBytes([6u8; 32]));
+            set_caller(accounts.charlie);
+            factory.cancel_code_hash();
+        }
+
+        #[ink::test]
+        fn ownership_moves_once_accepted() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.transfer_ownership(accounts.bob);
+            assert_eq!(ink::env::test::recorded_events().into_iter().count(), 1);
+            assert_eq!(factory.get_owner(), accounts.alice);
+            assert_eq!(factory.get_pending_owner(), Some(accounts.bob));
+
+            set_caller(accounts.bob);
+            factory.accept_ownership();
+            assert_eq!(factory.get_owner(), accounts.bob);
+            assert_eq!(factory.get_pending_owner(), None);
+            factory.set_treasury(accounts.bob);
+            assert_eq!(factory.get_treasury(), accounts.bob);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "not pending owner")]
+        fn only_the_pending_owner_accepts() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.transfer_ownership(accounts.bob);
+            set_caller(accounts.charlie);
+            factory.accept_ownership();
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "only owner")]
+        fn previous_owner_loses_control() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.transfer_ownership(accounts.bob);
+            set_caller(accounts.bob);
+            factory.accept_ownership();
+            set_caller(accounts.alice);
+            factory.propose_code_hash(CodeHash::from([5u8; 32]), FixedBytes([6u8; 32]));
+        }
+
+        #[ink::test]
+        fn protocol_fee_follows_policy_and_exemptions() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            let token = Address::from([3u8; 20]);
+            factory.set_fee_bps(None, 30);
+            factory.set_fee_bps(Some(token), MAX_FEE_BPS);
+            factory.set_treasury(accounts.frank);
+
+            set_caller(accounts.bob);
+            let fee = |bps| Some(Split { recipient: accounts.frank, bps });
+            assert_eq!(factory.protocol_fee_for(Address::default()), fee(30));
+            assert_eq!(factory.protocol_fee_for(token), fee(MAX_FEE_BPS));
+            assert_eq!(factory.protocol_fee_for(Address::from([4u8; 20])), None);
+
+            set_caller(accounts.alice);
+            factory.set_fee_exempt(accounts.bob, true);
+            set_caller(accounts.bob);
+            assert!(factory.is_fee_exempt(accounts.bob));
+            assert_eq!(factory.protocol_fee_for(Address::default()), None);
+            assert_eq!(factory.protocol_fee_for(token), None);
+
+            set_caller(accounts.alice);
+            factory.set_fee_exempt(accounts.bob, false);
+            set_caller(accounts.bob);
+            assert_eq!(factory.protocol_fee_for(Address::default()), fee(30));
+        }
+
+        #[ink::test]
+        fn stakes_add_up_per_resolver_and_in_total() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            stake(&mut factory, accounts.bob, 300);
+            stake(&mut factory, accounts.bob, 50);
+            stake(&mut factory, accounts.charlie, 200);
+            assert_eq!(stake_of(&factory, accounts.bob), (U256::from(350), U256::from(0)));
+            assert_eq!(factory.total_staked, U256::from(550));
+
+            // Unstaking keeps funds in the total until they are withdrawn.
+            set_caller(accounts.bob);
+            factory.unstake(U256::from(100));
+            assert_eq!(stake_of(&factory, accounts.bob), (U256::from(250), U256::from(100)));
+            assert_eq!(factory.total_staked, U256::from(550));
+        }
+
+        #[ink::test]
+        fn unstaking_again_restarts_the_unbonding_delay() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            stake(&mut factory, accounts.bob, 300);
+            factory.unstake(U256::from(100));
+            let first_unlock = factory.get_resolver(accounts.bob).unwrap().unlock_at;
+
+            advance(5);
+            factory.unstake(U256::from(50));
+            let entry = factory.get_resolver(accounts.bob).unwrap();
+            assert_eq!(entry.unlock_at, first_unlock + 5);
+            assert_eq!(entry.unbonding, U256::from(150));
+
+            advance(10);
+            let before = balance(accounts.bob);
+            factory.withdraw_unstaked();
+            assert_eq!(balance(accounts.bob), before + U256::from(150));
+            assert_eq!(stake_of(&factory, accounts.bob), (U256::from(150), U256::from(0)));
+            assert_eq!(factory.total_staked, U256::from(150));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "still unbonding")]
+        fn withdraw_waits_for_the_latest_unstake() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            stake(&mut factory, accounts.bob, 300);
+            factory.unstake(U256::from(100));
+            advance(5);
+            factory.unstake(U256::from(50));
+            // The first unstake alone would be withdrawable now.
+            advance(5);
+            factory.withdraw_unstaked();
+        }
+
+        #[ink::test]
+        fn slash_takes_active_stake_before_unbonding_funds() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.set_treasury(accounts.frank);
+            stake(&mut factory, accounts.bob, 300);
+            factory.unstake(U256::from(200));
+
+            set_caller(accounts.alice);
+            let treasury = balance(accounts.frank);
+            factory.slash(accounts.bob, U256::from(150));
+            assert_eq!(stake_of(&factory, accounts.bob), (U256::from(0), U256::from(150)));
+            assert_eq!(balance(accounts.frank), treasury + U256::from(150));
+
+            // Capped at what is left.
+            factory.slash(accounts.bob, U256::from(1_000));
+            assert_eq!(stake_of(&factory, accounts.bob), (U256::from(0), U256::from(0)));
+            assert_eq!(balance(accounts.frank), treasury + U256::from(300));
+            assert_eq!(factory.total_staked, U256::from(0));
+        }
+
+        #[ink::test]
+        fn registration_needs_whitelist_and_min_active_stake() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.set_staking_policy(None, TokenAbi::default(), U256::from(200), 10);
+            stake(&mut factory, accounts.bob, 300);
+            assert!(!factory.is_registered_resolver(accounts.bob));
+
+            set_caller(accounts.alice);
+            factory.set_resolver_whitelisted(accounts.bob, true);
+            assert!(factory.is_registered_resolver(accounts.bob));
+
+            // Unbonding funds do not count towards the minimum.
+            set_caller(accounts.bob);
+            factory.unstake(U256::from(101));
+            assert!(!factory.is_registered_resolver(accounts.bob));
+            assert!(!factory.is_registered_resolver(accounts.charlie));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "taker is not a registered resolver")]
+        fn unregistered_taker_is_rejected_when_required() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            factory.set_takers_must_be_registered(true);
+            ink::env::test::set_value_transferred(U256::from(1_100));
+            let _ = factory.create_native_escrow(
+                accounts.bob,
+                Vec::new(),
+                FixedBytes([0u8; 32]),
+                FixedBytes([1u8; 32]),
+                Timelocks::new(2, 4, 6, 8),
+                U256::from(100),
+                0,
+                HashAlgorithm::Keccak256,
+                Some(accounts.charlie),
+                None,
+            );
+        }
+
+        #[ink::test]
+        fn identical_create_without_salt_is_rejected() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            let params = EscrowParams {
+                token: None,
+                token_abi: TokenAbi::default(),
+                amount: U256::from(1_000),
+                beneficiary: accounts.bob,
+                splits: Vec::new(),
+                order_hash: FixedBytes([0u8; 32]),
+                hashed_secret: FixedBytes([1u8; 32]),
+                timelocks: Timelocks::new(2, 4, 6, 8),
+                resolver_deposit: U256::from(100),
+                parts_amount: 0,
+                hash_algorithm: HashAlgorithm::Keccak256,
+                taker: None,
+            };
+            // Stands in for the first create; off-chain tests cannot instantiate the escrow.
+            let first = factory.compute_escrow_address(params.clone(), accounts.alice, None);
+            factory.record_created(first, params.hashed_secret, params.beneficiary);
+
+            ink::env::test::set_value_transferred(U256::from(1_100));
+            let create = |factory: &mut HtlcFactory, salt| {
+                factory.create_native_escrow(
+                    params.beneficiary,
+                    params.splits.clone(),
+                    params.order_hash,
+                    params.hashed_secret,
+                    params.timelocks,
+                    params.resolver_deposit,
+                    params.parts_amount,
+                    params.hash_algorithm,
+                    params.taker,
+                    salt,
+                )
+            };
+            assert_eq!(create(&mut factory, None), Err(FactoryError::EscrowExists));
+            assert_eq!(
+                create(&mut factory, Some(FixedBytes([0u8; 32]))),
+                Err(FactoryError::EscrowExists)
+            );
+            assert_eq!(factory.count_escrows_by_hashlock(params.hashed_secret), 1);
+        }
+
+        #[ink::test]
+        fn staking_policy_changes_limits_while_stakes_are_outstanding() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            let token = Some(Address::from([3u8; 20]));
+            // Nothing staked yet: the token may change back and forth.
+            factory.set_staking_policy(token, TokenAbi::Psp22, U256::from(10), 5);
+            factory.set_staking_policy(None, TokenAbi::default(), U256::from(10), 5);
+            stake(&mut factory, accounts.bob, 300);
+
+            set_caller(accounts.alice);
+            factory.set_staking_policy(None, TokenAbi::default(), U256::from(500), 20);
+            assert_eq!(
+                factory.get_staking_policy(),
+                (None, TokenAbi::default(), U256::from(500), 20)
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "stakes outstanding")]
+        fn staking_token_is_fixed_while_stakes_are_outstanding() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            stake(&mut factory, accounts.bob, 300);
+            set_caller(accounts.alice);
+            factory.set_staking_policy(
+                Some(Address::from([3u8; 20])),
+                TokenAbi::Psp22,
+                U256::from(0),
+                10,
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "fee too high")]
+        fn fee_above_the_cap_is_rejected() {
+            factory().set_fee_bps(None, MAX_FEE_BPS + 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "only admin")]
+        fn fee_policy_is_admin_only() {
+            let accounts = ink::env::test::default_accounts::<DefaultEnvironment>();
+            let mut factory = factory();
+            set_caller(accounts.bob);
+            factory.set_fee_bps(None, 30);
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use erc20_mock::erc20_mock::{Erc20Mock, Erc20MockRef};
+        use htlc_escrow::htlc_escrow::{ClaimError, HtlcEscrow};
+        use ink::env::DefaultEnvironment;
+        use ink::sol::DynBytes;
+        use ink_e2e::ContractsBackend;
+        use standard_psp22::standard_psp22::{StandardPsp22, StandardPsp22Ref, PSP22 as _};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const SECRET: FixedBytes<32> = FixedBytes([7u8; 32]);
+
+        /// keccak256 of the escrow code built for this test run followed by the
+        /// `new_for_factory` selector, as `make escrow-init-code-hash` computes it.
+        fn escrow_init_code_hash() -> FixedBytes<32> {
+            let code_path = ink_e2e::build_root_and_contract_dependencies(Vec::new())
+                .into_iter()
+                .find(|path| path.file_stem() == Some("htlc_escrow".as_ref()))
+                .expect("htlc_escrow not built");
+            let mut input = std::fs::read(code_path).expect("htlc_escrow code");
+            input.extend_from_slice(&ink::selector_bytes!("new_for_factory"));
+            let mut out = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&input, &mut out);
+            FixedBytes(out)
+        }
+
+        /// An escrow deployed without its tokens cannot be claimed until `deposit_psp22`
+        /// moves them in through the factory.
+        #[ink_e2e::test]
+        async fn deposit_psp22_funds_an_existing_escrow<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let amount = 1_000u128;
+            let deposit = U256::from(1_000_000_000u64);
+            let beneficiary =
+                ink_e2e::address::<DefaultEnvironment>(ink_e2e::Sr25519Keyring::Bob);
+            let escrow_code = client
+                .upload("htlc_escrow", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("escrow upload failed");
+            let mut factory_ctor =
+                HtlcFactoryRef::new(escrow_code.code_hash, escrow_init_code_hash(), 100, 600, 10);
+            let factory = client
+                .instantiate("htlc_factory", &ink_e2e::alice(), &mut factory_ctor)
+                .submit()
+                .await
+                .expect("factory instantiate failed");
+            let mut factory_call = factory.call_builder::<HtlcFactory>();
+
+            let mut token_ctor = StandardPsp22Ref::new(1_000_000);
+            let token = client
+                .instantiate("standard_psp22", &ink_e2e::alice(), &mut token_ctor)
+                .submit()
+                .await
+                .expect("token instantiate failed");
+            let mut token_call = token.call_builder::<StandardPsp22>();
+
+            let mut hashed = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&SECRET.0, &mut hashed);
+            let mut escrow_ctor = HtlcEscrowRef::new_psp22(
+                token.addr,
+                TokenAbi::Psp22,
+                U256::from(amount),
+                beneficiary,
+                Vec::new(),
+                None,
+                FixedBytes([0u8; 32]),
+                FixedBytes(hashed),
+                Timelocks::new(0, 0, 1_000, 2_000),
+                deposit,
+                0,
+                HashAlgorithm::Keccak256,
+                None,
+                None,
+                10_000,
+            );
+            let escrow = client
+                .instantiate("htlc_escrow", &ink_e2e::alice(), &mut escrow_ctor)
+                .value(deposit)
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let mut escrow_call = escrow.call_builder::<HtlcEscrow>();
+
+            let info = client
+                .call(&ink_e2e::alice(), &escrow_call.get_info())
+                .dry_run()
+                .await?;
+            assert!(!info.return_value().funded);
+            let early = client
+                .call(&ink_e2e::charlie(), &escrow_call.claim(SECRET))
+                .dry_run()
+                .await?;
+            assert_eq!(early.return_value(), Err(ClaimError::NotFunded));
+
+            let approve = token_call.approve(factory.addr, amount);
+            client.call(&ink_e2e::alice(), &approve).submit().await?;
+            let fund = factory_call.deposit_psp22(
+                token.addr,
+                TokenAbi::Psp22,
+                escrow.addr,
+                U256::from(amount),
+            );
+            client.call(&ink_e2e::alice(), &fund).submit().await?;
+
+            let funded = client
+                .call(&ink_e2e::alice(), &escrow_call.verify_funding())
+                .submit()
+                .await?;
+            assert!(funded.return_value());
+            let info = client
+                .call(&ink_e2e::alice(), &escrow_call.get_info())
+                .dry_run()
+                .await?;
+            assert!(info.return_value().funded);
+
+            let claimed = client
+                .call(&ink_e2e::charlie(), &escrow_call.claim(SECRET))
+                .submit()
+                .await?;
+            assert_eq!(claimed.return_value(), Ok(()));
+            let paid = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(beneficiary))
+                .dry_run()
+                .await?;
+            assert_eq!(paid.return_value(), amount);
+            Ok(())
+        }
+
+        /// ERC-20 escrows are funded from the creator's allowance, pay the beneficiary on
+        /// claim and return the tokens to the initiator on a consented refund.
+        #[ink_e2e::test]
+        async fn erc20_escrows_are_funded_claimed_and_refunded<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let amount = U256::from(1_000);
+            let deposit = U256::from(1_000_000_000u64);
+            let initiator =
+                ink_e2e::address::<DefaultEnvironment>(ink_e2e::Sr25519Keyring::Alice);
+            let beneficiary =
+                ink_e2e::address::<DefaultEnvironment>(ink_e2e::Sr25519Keyring::Bob);
+            let escrow_code = client
+                .upload("htlc_escrow", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("escrow upload failed");
+            let mut factory_ctor =
+                HtlcFactoryRef::new(escrow_code.code_hash, escrow_init_code_hash(), 100, 600, 10);
+            let factory = client
+                .instantiate("htlc_factory", &ink_e2e::alice(), &mut factory_ctor)
+                .submit()
+                .await
+                .expect("factory instantiate failed");
+            let mut factory_call = factory.call_builder::<HtlcFactory>();
+
+            let mut token_ctor = Erc20MockRef::new(U256::from(1_000_000));
+            let token = client
+                .instantiate("erc20_mock", &ink_e2e::alice(), &mut token_ctor)
+                .submit()
+                .await
+                .expect("token instantiate failed");
+            let mut token_call = token.call_builder::<Erc20Mock>();
+            let approve = token_call.approve(factory.addr, amount + amount);
+            client.call(&ink_e2e::alice(), &approve).submit().await?;
+
+            let mut hashed = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&SECRET.0, &mut hashed);
+            let mut create = |salt: Option<FixedBytes<32>>| {
+                factory_call.create_erc20_escrow(
+                    token.addr,
+                    amount,
+                    beneficiary,
+                    Vec::new(),
+                    FixedBytes([0u8; 32]),
+                    FixedBytes(hashed),
+                    Timelocks::new(0, 0, 1_000, 2_000),
+                    deposit,
+                    0,
+                    HashAlgorithm::Keccak256,
+                    None,
+                    salt,
+                )
+            };
+
+            // Funding and claim.
+            let claimed_escrow = client
+                .call(&ink_e2e::alice(), &create(None))
+                .value(deposit)
+                .submit()
+                .await?
+                .return_value()
+                .expect("create_erc20_escrow failed");
+            let held = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(claimed_escrow))
+                .dry_run()
+                .await?;
+            assert_eq!(held.return_value(), amount);
+            let mut escrow_call = ink_e2e::create_call_builder::<HtlcEscrow>(claimed_escrow);
+            let info = client
+                .call(&ink_e2e::alice(), &escrow_call.get_info())
+                .dry_run()
+                .await?;
+            assert!(info.return_value().funded);
+            let claimed = client
+                .call(&ink_e2e::charlie(), &escrow_call.claim(SECRET))
+                .submit()
+                .await?;
+            assert_eq!(claimed.return_value(), Ok(()));
+            let paid = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(beneficiary))
+                .dry_run()
+                .await?;
+            assert_eq!(paid.return_value(), amount);
+
+            // Refund with the beneficiary's on-chain consent.
+            let refunded_escrow = client
+                .call(&ink_e2e::alice(), &create(Some(FixedBytes([1u8; 32]))))
+                .value(deposit)
+                .submit()
+                .await?
+                .return_value()
+                .expect("create_erc20_escrow failed");
+            let mut escrow_call = ink_e2e::create_call_builder::<HtlcEscrow>(refunded_escrow);
+            client
+                .call(&ink_e2e::bob(), &escrow_call.approve_cancel())
+                .submit()
+                .await?;
+            let before = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(initiator))
+                .dry_run()
+                .await?
+                .return_value();
+            let cancel = escrow_call.cancel_with_consent(DynBytes(Vec::new()));
+            let cancelled = client.call(&ink_e2e::alice(), &cancel).submit().await?;
+            assert_eq!(cancelled.return_value(), Ok(()));
+            let after = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(initiator))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(after, before + amount);
+            let left = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(refunded_escrow))
+                .dry_run()
+                .await?;
+            assert_eq!(left.return_value(), U256::from(0));
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn escrow_lands_on_the_computed_address<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let escrow_code = client
+                .upload("htlc_escrow", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("escrow upload failed");
+            let mut factory_ctor =
+                HtlcFactoryRef::new(escrow_code.code_hash, escrow_init_code_hash(), 100, 600, 10);
+            let factory = client
+                .instantiate("htlc_factory", &ink_e2e::alice(), &mut factory_ctor)
+                .submit()
+                .await
+                .expect("factory instantiate failed");
+            let mut call = factory.call_builder::<HtlcFactory>();
+
+            let initiator =
+                ink_e2e::address::<DefaultEnvironment>(ink_e2e::Sr25519Keyring::Alice);
+            let beneficiary =
+                ink_e2e::address::<DefaultEnvironment>(ink_e2e::Sr25519Keyring::Bob);
+            let deposit = U256::from(1_000_000_000u64);
+            let params = EscrowParams {
+                token: None,
+                token_abi: TokenAbi::default(),
+                amount: deposit,
+                beneficiary,
+                splits: Vec::new(),
+                order_hash: FixedBytes([0u8; 32]),
+                hashed_secret: FixedBytes([1u8; 32]),
+                timelocks: Timelocks::new(10, 20, 30, 40),
+                resolver_deposit: deposit,
+                parts_amount: 0,
+                hash_algorithm: HashAlgorithm::Keccak256,
+                taker: None,
+            };
+            let salt = Some(FixedBytes([5u8; 32]));
+            let query = call.compute_escrow_address(params.clone(), initiator, salt);
+            let predicted = client
+                .call(&ink_e2e::alice(), &query)
+                .dry_run()
+                .await?
+                .return_value();
+
+            let create = call.create_native_escrow(
+                params.beneficiary,
+                params.splits,
+                params.order_hash,
+                params.hashed_secret,
+                params.timelocks,
+                params.resolver_deposit,
+                params.parts_amount,
+                params.hash_algorithm,
+                params.taker,
+                salt,
+            );
+            let created = client
+                .call(&ink_e2e::alice(), &create)
+                .value(deposit + deposit)
+                .submit()
+                .await
+                .expect("create_native_escrow failed")
+                .return_value();
+            assert_eq!(created, Ok(predicted));
+            let created = predicted;
+
+            // The same params and salt again would land on the same address.
+            let again = client
+                .call(&ink_e2e::alice(), &create)
+                .value(deposit + deposit)
+                .dry_run()
+                .await?;
+            assert_eq!(again.return_value(), Err(FactoryError::EscrowExists));
+
+            let record = client
+                .call(&ink_e2e::alice(), &call.get_escrow_record(created))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(record.map(|record| record.initiator), Some(initiator));
+            Ok(())
+        }
     }
 }
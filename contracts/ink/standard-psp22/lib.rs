@@ -0,0 +1,111 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Minimal PSP22 used by the e2e tests: the standard `PSP22::*` selectors, `u128`
+/// amounts and `PSP22Error`, as third-party tokens implement them.
+#[ink::contract(env = ink::env::DefaultEnvironment)]
+pub mod standard_psp22 {
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+
+    /// Same layout as `htlc_escrow::PSP22Error`.
+    #[derive(scale::Encode, scale::Decode, scale_info::TypeInfo, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// The subset of the standard trait the escrow and factory use, plus `approve`.
+    #[ink::trait_definition]
+    pub trait PSP22 {
+        #[ink(message)]
+        fn balance_of(&self, owner: Address) -> u128;
+
+        #[ink(message)]
+        fn transfer(&mut self, to: Address, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: Address,
+            to: Address,
+            value: u128,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+
+        #[ink(message)]
+        fn approve(&mut self, spender: Address, value: u128) -> Result<(), PSP22Error>;
+    }
+
+    #[ink(storage)]
+    pub struct StandardPsp22 {
+        balances: Mapping<Address, u128>,
+        allowances: Mapping<(Address, Address), u128>,
+    }
+
+    impl StandardPsp22 {
+        #[ink(constructor)]
+        pub fn new(initial_supply: u128) -> Self {
+            let mut balances = Mapping::default();
+            balances.insert(Self::env().caller(), &initial_supply);
+            Self {
+                balances,
+                allowances: Mapping::default(),
+            }
+        }
+
+        fn move_balance(&mut self, from: Address, to: Address, value: u128) -> Result<(), PSP22Error> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            Ok(())
+        }
+    }
+
+    impl PSP22 for StandardPsp22 {
+        #[ink(message)]
+        fn balance_of(&self, owner: Address) -> u128 {
+            self.balances.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: Address, value: u128, _data: Vec<u8>) -> Result<(), PSP22Error> {
+            let from = self.env().caller();
+            self.move_balance(from, to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: Address,
+            to: Address,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowances.get((from, spender)).unwrap_or(0);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.move_balance(from, to, value)?;
+            self.allowances.insert((from, spender), &(allowance - value));
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: Address, value: u128) -> Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &value);
+            Ok(())
+        }
+    }
+}
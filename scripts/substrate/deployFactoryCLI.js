@@ -5,7 +5,7 @@ const executionDirectory = "../../contracts/ink/htlc-factory/";
 const command = `cargo contract call \
   --contract 0x75e742d4c46cd85f3dc1866335394b53eada6867 \
   --message create_native_escrow \
-  --args 0x6bd3cd4c04b9899c0fee3db05b4971256e153b70 0x86c47f76ff4a6cb8ee9c172982eda47e895262b5a6a7582aaf7d97295ce1d8d4 1000 1000000000000 None \
+  --args 0x6bd3cd4c04b9899c0fee3db05b4971256e153b70 "[]" 0x0000000000000000000000000000000000000000000000000000000000000000 0x86c47f76ff4a6cb8ee9c172982eda47e895262b5a6a7582aaf7d97295ce1d8d4 "Timelocks(95073795035563949188109775667210)" 1000000000000 0 Keccak256 None None \
   --value 6000000000000 \
   --skip-confirm \
   -x \
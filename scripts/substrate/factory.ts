@@ -7,8 +7,9 @@
  *      Read last created escrow from factory (get_last_escrow)
  *  - create-native
  *      Create native escrow via factory:
- *      --beneficiary 0x...H160 --hash 0x...64hex --expiry 12345 --deposit 1000 --locked 5000 --salt None|Some(0x...64hex)
- *      (value attached = deposit + locked)
+ *      --beneficiary 0x...H160 --hash 0x...64hex --timelocks <packed u256> --deposit 1000 --locked 5000
+ *      [--orderHash 0x...64hex] [--taker 0x...H160] [--salt None|Some(0x...64hex)]
+ *      (value attached = deposit + locked; timelocks pack stage offsets, see htlc_escrow::Timelocks)
  *  - get-info
  *      Read escrow.get_info from given --escrow 0x...
  *  - claim
@@ -43,8 +44,8 @@ function parseArgs(argv: string[]): { cmd: string; args: Args } {
   const args: Args = {};
   let cmd = '';
   const flags = new Set([
-    '--endpoint', '--factory', '--escrow', '--beneficiary', '--hash', '--expiry', '--deposit', '--locked',
-    '--salt', '--secret', '--json', '--password', '--suri', '--factoryMetadata', '--escrowMetadata'
+    '--endpoint', '--factory', '--escrow', '--beneficiary', '--hash', '--timelocks', '--deposit', '--locked',
+    '--orderHash', '--taker', '--salt', '--secret', '--json', '--password', '--suri', '--factoryMetadata', '--escrowMetadata'
   ]);
   for (let i = 2; i < argv.length; i++) {
     const a = argv[i];
@@ -198,7 +199,11 @@ async function main() {
     if (!factoryAddr) throw new Error('--factory is required');
     const beneficiary = (args.beneficiary || process.env.APP_BENEFICIARY)!;
     const hashed = (args.hash || process.env.APP_HASH)!;
-    const expiry = BigInt(args.expiry || process.env.APP_EXPIRY!);
+    // Default: private withdrawal +10, public withdrawal +500, private cancellation +1000,
+    // public cancellation +1200 blocks (same as the Makefile).
+    const timelocks = BigInt(args.timelocks || process.env.APP_TIMELOCKS || '95073795035563949188109775667210');
+    const orderHash = args.orderHash || process.env.APP_ORDER_HASH || '0x' + '00'.repeat(32);
+    const taker = args.taker || process.env.APP_TAKER || null;
     const deposit = BigInt(args.deposit || process.env.APP_DEPOSIT!);
     const locked = BigInt(args.locked || process.env.APP_LOCKED!);
     const saltOpt = parseOptionBytes32(args.salt || process.env.APP_SALT);
@@ -206,19 +211,24 @@ async function main() {
     // Attached value = locked + deposit
     const value = deposit + locked;
 
-    // Prepare params: (beneficiary: Address/H160 hex string), (hashed_secret: [u8;32] hex), expiry: u64, resolver_deposit: U256, salt: Option<[u8;32]>
-    // For polkadot.js, pass hex strings or u8a for fixed bytes; U256 numbers passed as bigint decimal (codec supports).
+    // Prepare params in create_native_escrow order. For polkadot.js, pass hex strings or u8a for
+    // fixed bytes; U256 numbers passed as bigint decimal (codec supports).
     const params: any[] = [
       beneficiary,                       // Address (assumed hex for H160 environment)
-      hashed,                            // [u8;32] hex
-      expiry,                            // u64
-      deposit,                           // U256
+      [],                                // splits: Vec<Split>
+      orderHash,                         // order_hash: [u8;32] hex
+      hashed,                            // hashed_secret: [u8;32] hex
+      timelocks,                         // Timelocks(U256)
+      deposit,                           // resolver_deposit: U256
+      0,                                 // parts_amount: u16 (single fill)
+      'Keccak256',                       // hash_algorithm
+      taker,                             // taker: Option<Address>
       saltOpt                            // Option<[u8;32]> -> null or u8a
     ];
 
     const { contract, gasLimit, storageDepositLimit, output } = await dryRunFactory('create_native_escrow', value, params);
     if (output) {
-      console.log('dry-run return (escrow address?):', output.toString());
+      console.log('dry-run return (Ok(escrow) or Err(EscrowExists)):', output.toString());
     }
     const tx = contract.tx.create_native_escrow({ value, gasLimit, storageDepositLimit }, ...params);
     const unsub = await tx.signAndSend(signer, (ev: any) => {
@@ -0,0 +1,24 @@
+// Prints the escrow_init_code_hash the factory constructor (and propose_code_hash) expects:
+// keccak256(escrow code ++ new_for_factory constructor input), as hashed by pallet-revive for CREATE2.
+// Usage: node initCodeHash.js [path/to/htlc_escrow.polkavm]
+const fs = require("fs");
+const path = require("path");
+const { blake2AsU8a, keccakAsHex } = require("@polkadot/util-crypto");
+const { stringToU8a, u8aConcat, u8aToHex } = require("@polkadot/util");
+
+const codePath =
+  process.argv[2] ||
+  path.resolve(__dirname, "../../contracts/ink/htlc-escrow/target/ink/htlc_escrow.polkavm");
+
+if (!fs.existsSync(codePath)) {
+  console.error(`No escrow code at ${codePath}. Run 'cargo contract build --release' in contracts/ink/htlc-escrow first.`);
+  process.exit(1);
+}
+
+const code = fs.readFileSync(codePath);
+// ink! selector: first 4 bytes of blake2b-256 of the constructor name; new_for_factory takes no arguments.
+const selector = blake2AsU8a(stringToU8a("new_for_factory"), 256).slice(0, 4);
+
+console.error(`code:     ${codePath} (${code.length} bytes)`);
+console.error(`selector: ${u8aToHex(selector)}`);
+console.log(keccakAsHex(u8aConcat(code, selector)));